@@ -1,8 +1,35 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use std::any::Any;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
 use std::sync::{Arc, Mutex};
+use regex::RegexBuilder;
+
+/// 推理循环默认的最大轮次，超过后视为规则集无法收敛
+const DEFAULT_MAX_CYCLES: usize = 100;
+
+mod path;
+use path::{get_field_path, set_field_path_with_seed};
+
+mod registry;
+pub use registry::{ActionHandler, ConditionEvaluator};
+
+mod transport;
+pub use transport::{ReqwestTransport, Transport};
+use transport::call_with_retry;
+
+mod parser;
+pub use parser::RuleFormat;
+use std::time::Duration;
+use std::future::Future;
+use std::pin::Pin;
+
+/// 外部服务调用的默认超时时间（毫秒）
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+/// 外部服务调用失败后的默认重试次数
+const DEFAULT_MAX_RETRIES: u32 = 2;
 
 /// 规则引擎错误类型
 #[derive(Error, Debug)]
@@ -33,6 +60,11 @@ pub enum Condition {
     GreaterThan { field: String, value: Value },
     LessThan { field: String, value: Value },
     Contains { field: String, value: Value },
+    /// 正则匹配：`pattern` 中的具名捕获组会被写入 `RuleContext.outputs`，
+    /// 键名为 `field_<groupname>`，便于后续动作引用匹配到的子串。
+    Regex { field: String, pattern: String, ignore_case: bool },
+    /// 调用通过 `RuleExecutor::register_condition` 注册的自定义条件
+    Custom { name: String, params: HashMap<String, Value> },
     And(Vec<Condition>),
     Or(Vec<Condition>),
     Not(Box<Condition>),
@@ -43,8 +75,26 @@ pub enum Condition {
 pub enum Action {
     Log { message: String },
     UpdateField { field: String, value: Value },
-    CallExternalService { endpoint: String, payload: HashMap<String, Value> },
-    SendEvent { event_type: String, data: HashMap<String, Value> },
+    CallExternalService {
+        endpoint: String,
+        payload: HashMap<String, Value>,
+        /// 本次调用的超时时间（毫秒），缺省时使用执行器的默认值
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+        /// 失败后的最大重试次数，缺省时使用执行器的默认值
+        #[serde(default)]
+        max_retries: Option<u32>,
+    },
+    SendEvent {
+        event_type: String,
+        data: HashMap<String, Value>,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+        #[serde(default)]
+        max_retries: Option<u32>,
+    },
+    /// 调用通过 `RuleExecutor::register_action` 注册的自定义动作
+    Custom { name: String, params: HashMap<String, Value> },
     Composite(Vec<Action>),
 }
 
@@ -60,6 +110,43 @@ pub enum Value {
     Null,
 }
 
+impl Value {
+    /// 转换为 `serde_json::Value`，用于向外部服务发送请求体
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::String(s) => serde_json::Value::String(s.clone()),
+            Value::Int(i) => serde_json::Value::from(*i),
+            Value::Float(f) => serde_json::json!(*f),
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::List(list) => serde_json::Value::Array(list.iter().map(Value::to_json).collect()),
+            Value::Map(map) => serde_json::Value::Object(
+                map.iter().map(|(k, v)| (k.clone(), v.to_json())).collect()
+            ),
+            Value::Null => serde_json::Value::Null,
+        }
+    }
+
+    /// 从 `serde_json::Value` 解析为我们自己的 `Value` 树，用于解析外部服务的响应
+    pub fn from_json(json: &serde_json::Value) -> Value {
+        match json {
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Value::Int(i)
+                } else {
+                    Value::Float(n.as_f64().unwrap_or(0.0))
+                }
+            }
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Array(arr) => Value::List(arr.iter().map(Value::from_json).collect()),
+            serde_json::Value::Object(obj) => Value::Map(
+                obj.iter().map(|(k, v)| (k.clone(), Value::from_json(v))).collect()
+            ),
+            serde_json::Value::Null => Value::Null,
+        }
+    }
+}
+
 /// 规则定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
@@ -85,6 +172,16 @@ pub struct RuleExecutor {
     rules: Vec<Rule>,
     rule_cache: HashMap<String, Rule>,
     context: Arc<Mutex<RuleContext>>,
+    /// 已编译的正则缓存，键为 `pattern` 与 `ignore_case` 的组合，避免每次 `execute` 都重新编译
+    regex_cache: Mutex<HashMap<String, regex::Regex>>,
+    /// 正向推理的最大轮次，防止规则集无法收敛时无限循环
+    max_cycles: usize,
+    /// `Condition::Custom { name, .. }` 到自定义求值器的注册表
+    condition_registry: HashMap<String, Arc<dyn ConditionEvaluator>>,
+    /// `Action::Custom { name, .. }` 到自定义执行器的注册表
+    action_registry: HashMap<String, Arc<dyn ActionHandler>>,
+    /// `execute_async` 用于发起外部调用的传输层，默认是真实的HTTP实现
+    transport: Arc<dyn Transport>,
 }
 
 impl RuleExecutor {
@@ -94,8 +191,196 @@ impl RuleExecutor {
             rules: Vec::new(),
             rule_cache: HashMap::new(),
             context: Arc::new(Mutex::new(RuleContext::default())),
+            regex_cache: Mutex::new(HashMap::new()),
+            max_cycles: DEFAULT_MAX_CYCLES,
+            condition_registry: HashMap::new(),
+            action_registry: HashMap::new(),
+            transport: Arc::new(ReqwestTransport::new()),
+        }
+    }
+
+    /// 设置推理循环允许的最大轮次
+    pub fn with_max_cycles(mut self, max_cycles: usize) -> Self {
+        self.max_cycles = max_cycles;
+        self
+    }
+
+    /// 替换 `execute_async` 使用的传输层（默认基于 reqwest 发起真实HTTP请求）
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// 注册一个自定义条件求值器，供 `Condition::Custom { name, .. }` 分发使用
+    pub fn register_condition(&mut self, name: impl Into<String>, evaluator: Arc<dyn ConditionEvaluator>) {
+        self.condition_registry.insert(name.into(), evaluator);
+    }
+
+    /// 注册一个自定义动作执行器，供 `Action::Custom { name, .. }` 分发使用
+    pub fn register_action(&mut self, name: impl Into<String>, handler: Arc<dyn ActionHandler>) {
+        self.action_registry.insert(name.into(), handler);
+    }
+
+    /// 从JSON/YAML文本加载规则（单条规则对象或规则数组均可）并加入引擎，
+    /// 与已有规则ID相同的规则会替换旧规则，返回本次加载的规则数量。
+    /// 用于从配置文件加载/热更新规则集。
+    pub fn load_rules_from_str(&mut self, format: RuleFormat, text: &str) -> Result<usize, RuleEngineError> {
+        let rules = parser::parse_rules_from_str(format, text)?;
+        let count = rules.len();
+        for rule in rules {
+            self.update_rule(rule);
+        }
+        Ok(count)
+    }
+
+    /// 把一个 `Value` 递归序列化为按键排序的规范字符串，使逻辑上相同的
+    /// `Value::Map`/`Value::List` 无论其内部 `HashMap` 的实际迭代顺序如何，
+    /// 都产生完全一样的表示。派生的 `Debug` 会按 `HashMap` 自身随机种子的
+    /// 迭代顺序打印键值对，不能直接用来做哈希。
+    fn canonicalize_value(value: &Value, out: &mut String) {
+        match value {
+            Value::String(s) => {
+                out.push('"');
+                out.push_str(s);
+                out.push('"');
+            }
+            Value::Int(i) => out.push_str(&i.to_string()),
+            Value::Float(f) => out.push_str(&f.to_string()),
+            Value::Bool(b) => out.push_str(&b.to_string()),
+            Value::Null => out.push_str("null"),
+            Value::List(list) => {
+                out.push('[');
+                for item in list {
+                    Self::canonicalize_value(item, out);
+                    out.push(',');
+                }
+                out.push(']');
+            }
+            Value::Map(map) => {
+                let sorted: BTreeMap<&String, &Value> = map.iter().collect();
+                out.push('{');
+                for (k, v) in sorted {
+                    out.push('"');
+                    out.push_str(k);
+                    out.push_str("\":");
+                    Self::canonicalize_value(v, out);
+                    out.push(',');
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    /// 对工作事实集求一个稳定的哈希值，用于判断某条规则是否已在相同事实状态下触发过。
+    /// 必须基于按键排序的规范表示，而不是 `Value` 的派生 `Debug`（其内部
+    /// `HashMap` 迭代顺序依赖随机种子，逻辑相同的事实集可能打印出不同顺序）。
+    fn hash_facts(facts: &HashMap<String, Value>) -> u64 {
+        let sorted: BTreeMap<&String, &Value> = facts.iter().collect();
+        let mut hasher = DefaultHasher::new();
+        let mut buf = String::new();
+        for (k, v) in sorted {
+            k.hash(&mut hasher);
+            buf.clear();
+            Self::canonicalize_value(v, &mut buf);
+            buf.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// 在当前工作事实集下做一次完整扫描：按优先级依次评估尚未针对该事实状态
+    /// 触发过的规则，返回第一条条件成立的规则（连同它命中的正则捕获）。
+    /// 条件为假的规则会被立即标记为"已针对该状态评估过"，这样一整轮扫描
+    /// 只消耗一个 cycle 预算，而不是每条被跳过的规则各消耗一个。
+    /// 如果扫描完所有规则都没有可触发的，返回 `None`，表示已到达不动点。
+    fn find_fireable_rule(
+        &self,
+        sorted_rules: &[Rule],
+        working: &HashMap<String, Value>,
+        fired: &mut HashSet<(String, u64)>,
+        facts_hash: u64,
+    ) -> Result<Option<(Rule, (String, u64), HashMap<String, Value>)>, RuleEngineError> {
+        for rule in sorted_rules {
+            if !rule.enabled {
+                continue;
+            }
+            let key = (rule.id.clone(), facts_hash);
+            if fired.contains(&key) {
+                continue;
+            }
+
+            let mut captures = HashMap::new();
+            if self.evaluate_condition(&rule.condition, working, &mut captures)? {
+                return Ok(Some((rule.clone(), key, captures)));
+            }
+            fired.insert(key);
+        }
+        Ok(None)
+    }
+
+    /// 一次推理迭代中，执行动作之前的公共准备步骤：检查轮次预算、在当前工作
+    /// 事实集下找到下一条可触发规则，并为其构造好带初始捕获的 `RuleContext`。
+    /// 返回 `None` 表示已到达不动点。被 `execute`/`execute_async` 共用，
+    /// 两者唯一的区别只在于动作本身是同步执行还是异步执行。
+    fn begin_cycle(
+        &self,
+        sorted_rules: &[Rule],
+        working: &HashMap<String, Value>,
+        outputs: &HashMap<String, Value>,
+        fired: &mut HashSet<(String, u64)>,
+        cycle: usize,
+    ) -> Result<Option<(Rule, RuleContext)>, RuleEngineError> {
+        // 轮次预算在每次迭代开始时检查，无论上一轮是触发了规则还是整轮扫描都没有
+        // 发现可触发规则，只要超过预算就必须报错，不能悄悄地跌出循环。
+        if cycle >= self.max_cycles {
+            return Err(RuleEngineError::ExecutionError(
+                format!("推理循环超过最大轮次限制 ({})，规则集可能无法收敛", self.max_cycles)
+            ));
+        }
+
+        let facts_hash = Self::hash_facts(working);
+        let Some((rule, key, captures)) = self.find_fireable_rule(sorted_rules, working, fired, facts_hash)? else {
+            return Ok(None);
+        };
+
+        println!("规则触发 (第{}轮): {} ({})", cycle + 1, rule.name, rule.id);
+        fired.insert(key);
+
+        let mut context = RuleContext {
+            facts: working.clone(),
+            outputs: outputs.clone(),
+            external_data: HashMap::new(),
+        };
+        for (k, v) in captures {
+            context.outputs.insert(k, v);
+        }
+
+        Ok(Some((rule, context)))
+    }
+
+    /// 把一次迭代执行动作后的 `RuleContext.outputs` 合并回累计输出与工作事实集，
+    /// 使得后续轮次里原本不满足的规则条件可能变为满足。与 `begin_cycle` 搭配，
+    /// 被 `execute`/`execute_async` 共用。
+    fn finish_cycle(working: &mut HashMap<String, Value>, outputs: &mut HashMap<String, Value>, context: RuleContext) {
+        *outputs = context.outputs;
+        for (k, v) in outputs.iter() {
+            working.insert(k.clone(), v.clone());
         }
     }
+
+    /// 获取（或编译并缓存）给定模式的正则表达式
+    fn compiled_regex(&self, pattern: &str, ignore_case: bool) -> Result<regex::Regex, RuleEngineError> {
+        let cache_key = format!("{}\0{}", ignore_case, pattern);
+        let mut cache = self.regex_cache.lock().unwrap();
+        if let Some(re) = cache.get(&cache_key) {
+            return Ok(re.clone());
+        }
+        let re = RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| RuleEngineError::EvaluationError(format!("正则表达式编译失败 '{}': {}", pattern, e)))?;
+        cache.insert(cache_key, re.clone());
+        Ok(re)
+    }
     
     /// 添加规则
     pub fn add_rule(&mut self, rule: Rule) {
@@ -122,18 +407,19 @@ impl RuleExecutor {
         self.add_rule(rule);
     }
     
-    /// 评估条件
+    /// 评估条件。`captures` 是一个暂存区：`Regex` 命中时把具名捕获写进这里，
+    /// 但调用方只有在确认整个条件最终为真、规则真正触发时才会把它合并进
+    /// `RuleContext.outputs`，避免未触发的分支（例如 `And` 里跟在后面的条件
+    /// 为假）把捕获结果泄漏到输出中。
     fn evaluate_condition(
-        &self, 
-        condition: &Condition, 
-        facts: &HashMap<String, Value>
+        &self,
+        condition: &Condition,
+        facts: &HashMap<String, Value>,
+        captures: &mut HashMap<String, Value>,
     ) -> Result<bool, RuleEngineError> {
         match condition {
             Condition::Equals { field, value } => {
-                let fact_value = facts.get(field)
-                    .ok_or_else(|| RuleEngineError::EvaluationError(
-                        format!("字段不存在: {}", field)
-                    ))?;
+                let fact_value = get_field_path(facts, field)?;
                 
                 if fact_value == value {
                     Ok(true)
@@ -143,10 +429,7 @@ impl RuleExecutor {
             }
             
             Condition::GreaterThan { field, value } => {
-                let fact_value = facts.get(field)
-                    .ok_or_else(|| RuleEngineError::EvaluationError(
-                        format!("字段不存在: {}", field)
-                    ))?;
+                let fact_value = get_field_path(facts, field)?;
                 
                 match (fact_value, value) {
                     (Value::Int(a), Value::Int(b)) => Ok(a > b),
@@ -160,10 +443,7 @@ impl RuleExecutor {
             }
             
             Condition::LessThan { field, value } => {
-                let fact_value = facts.get(field)
-                    .ok_or_else(|| RuleEngineError::EvaluationError(
-                        format!("字段不存在: {}", field)
-                    ))?;
+                let fact_value = get_field_path(facts, field)?;
                 
                 match (fact_value, value) {
                     (Value::Int(a), Value::Int(b)) => Ok(a < b),
@@ -177,10 +457,7 @@ impl RuleExecutor {
             }
             
             Condition::Contains { field, value } => {
-                let fact_value = facts.get(field)
-                    .ok_or_else(|| RuleEngineError::EvaluationError(
-                        format!("字段不存在: {}", field)
-                    ))?;
+                let fact_value = get_field_path(facts, field)?;
                 
                 match fact_value {
                     Value::String(s) => {
@@ -198,27 +475,65 @@ impl RuleExecutor {
                     )),
                 }
             }
-            
+
+            Condition::Regex { field, pattern, ignore_case } => {
+                let fact_value = get_field_path(facts, field)?;
+
+                let text = match fact_value {
+                    Value::String(s) => s,
+                    _ => return Err(RuleEngineError::TypeMismatch(
+                        format!("字段 {} 的正则匹配要求字符串类型", field)
+                    )),
+                };
+
+                let re = self.compiled_regex(pattern, *ignore_case)?;
+                match re.captures(text) {
+                    Some(matched) => {
+                        for name in re.capture_names().flatten() {
+                            if let Some(m) = matched.name(name) {
+                                captures.insert(
+                                    format!("{}_{}", field, name),
+                                    Value::String(m.as_str().to_string()),
+                                );
+                            }
+                        }
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            }
+
+            Condition::Custom { name, params } => {
+                let evaluator = self.condition_registry.get(name).ok_or_else(|| {
+                    RuleEngineError::EvaluationError(format!("未注册的自定义条件: {}", name))
+                })?;
+                evaluator.eval(params, facts)
+            }
+
             Condition::And(conditions) => {
+                let mut local = HashMap::new();
                 for cond in conditions {
-                    if !self.evaluate_condition(cond, facts)? {
+                    if !self.evaluate_condition(cond, facts, &mut local)? {
                         return Ok(false);
                     }
                 }
+                captures.extend(local);
                 Ok(true)
             }
-            
+
             Condition::Or(conditions) => {
                 for cond in conditions {
-                    if self.evaluate_condition(cond, facts)? {
+                    let mut local = HashMap::new();
+                    if self.evaluate_condition(cond, facts, &mut local)? {
+                        captures.extend(local);
                         return Ok(true);
                     }
                 }
                 Ok(false)
             }
-            
+
             Condition::Not(condition) => {
-                let result = self.evaluate_condition(condition, facts)?;
+                let result = self.evaluate_condition(condition, facts, captures)?;
                 Ok(!result)
             }
         }
@@ -237,12 +552,15 @@ impl RuleExecutor {
             }
             
             Action::UpdateField { field, value } => {
-                context.outputs.insert(field.clone(), value.clone());
+                // `context.outputs` 起初与 `context.facts` 不同步（可能完全为空），
+                // 所以写入嵌套/索引路径前要先从 `facts` 种入该顶层键的当前值，
+                // 否则会把该字段下未涉及的兄弟数据（其它Map键、List的其它下标）覆盖掉。
+                set_field_path_with_seed(&mut context.outputs, &context.facts, field, value.clone())?;
                 Ok(())
             }
             
-            Action::CallExternalService { endpoint, payload } => {
-                // 实际应用中这里会调用外部服务
+            Action::CallExternalService { endpoint, payload, .. } => {
+                // 同步执行仅做模拟；真正发起HTTP调用见 `execute_async`/`execute_action_async`
                 println!("调用外部服务: {}, 参数: {:?}", endpoint, payload);
                 // 模拟成功响应
                 context.outputs.insert(
@@ -251,13 +569,20 @@ impl RuleExecutor {
                 );
                 Ok(())
             }
-            
-            Action::SendEvent { event_type, data } => {
+
+            Action::SendEvent { event_type, data, .. } => {
                 // 实际应用中这里会发送事件
                 println!("发送事件: {}, 数据: {:?}", event_type, data);
                 Ok(())
             }
             
+            Action::Custom { name, params } => {
+                let handler = self.action_registry.get(name).ok_or_else(|| {
+                    RuleEngineError::ActionFailed(format!("未注册的自定义动作: {}", name))
+                })?;
+                handler.run(params, context)
+            }
+
             Action::Composite(actions) => {
                 for action in actions {
                     self.execute_action(action, context)?;
@@ -266,33 +591,119 @@ impl RuleExecutor {
             }
         }
     }
-    
-    /// 执行规则
+
+    /// `execute_action` 的异步版本：`CallExternalService`/`SendEvent` 会通过
+    /// `self.transport` 发起真实的外部调用（带超时与退避重试），其余动作与
+    /// 同步版本的行为一致。
+    fn execute_action_async<'a>(
+        &'a self,
+        action: &'a Action,
+        context: &'a mut RuleContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), RuleEngineError>> + 'a>> {
+        Box::pin(async move {
+            match action {
+                Action::CallExternalService { endpoint, payload, timeout_ms, max_retries } => {
+                    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+                    let retries = max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+                    let response = call_with_retry(
+                        self.transport.as_ref(),
+                        endpoint,
+                        payload,
+                        timeout,
+                        retries,
+                    ).await?;
+                    context.outputs.insert(format!("{}_response", endpoint.replace('/', "_")), response);
+                    Ok(())
+                }
+
+                Action::SendEvent { event_type, data, timeout_ms, max_retries } => {
+                    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+                    let retries = max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+                    let response = call_with_retry(
+                        self.transport.as_ref(),
+                        event_type,
+                        data,
+                        timeout,
+                        retries,
+                    ).await?;
+                    context.outputs.insert(format!("{}_response", event_type.replace('/', "_")), response);
+                    Ok(())
+                }
+
+                Action::Composite(actions) => {
+                    for action in actions {
+                        self.execute_action_async(action, context).await?;
+                    }
+                    Ok(())
+                }
+
+                // 其余动作没有异步I/O，直接复用同步实现
+                other => self.execute_action(other, context),
+            }
+        })
+    }
+
+    /// `execute` 的异步版本：推理循环与 `execute` 完全一致，唯一区别是
+    /// `Action::CallExternalService`/`Action::SendEvent` 通过 `execute_action_async`
+    /// 发起真实的外部调用。
+    pub async fn execute_async(&self, facts: &HashMap<String, Value>) -> Result<HashMap<String, Value>, RuleEngineError> {
+        let mut working = facts.clone();
+        let mut outputs = HashMap::new();
+
+        let mut sorted_rules = self.rules.clone();
+        sorted_rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+
+        let mut fired: HashSet<(String, u64)> = HashSet::new();
+        let mut cycle = 0usize;
+
+        loop {
+            let Some((rule, mut context)) = self.begin_cycle(&sorted_rules, &working, &outputs, &mut fired, cycle)? else {
+                break;
+            };
+
+            for action in &rule.actions {
+                self.execute_action_async(action, &mut context).await?;
+            }
+            Self::finish_cycle(&mut working, &mut outputs, context);
+
+            cycle += 1;
+        }
+
+        Ok(outputs)
+    }
+
+    /// 执行规则：按正向推理（forward-chaining）方式反复求值，
+    /// 直到没有规则能在当前工作事实集下新触发为止（不动点）。
+    ///
+    /// `Action::UpdateField` 写入的结果会被合并回工作事实集，使得后续轮次里
+    /// 原本不满足的规则条件可能变为满足，从而被调度触发；同一条规则在同一个
+    /// 事实状态下只会触发一次，避免死循环。
     pub fn execute(&self, facts: &HashMap<String, Value>) -> Result<HashMap<String, Value>, RuleEngineError> {
-        let mut context = RuleContext {
-            facts: facts.clone(),
-            outputs: HashMap::new(),
-            external_data: HashMap::new(),
-        };
-        
+        let mut working = facts.clone();
+        let mut outputs = HashMap::new();
+
         // 按优先级排序规则（优先级数值高的先执行）
         let mut sorted_rules = self.rules.clone();
         sorted_rules.sort_by(|a, b| b.priority.cmp(&a.priority));
-        
-        for rule in sorted_rules {
-            if !rule.enabled {
-                continue;
-            }
-            
-            if self.evaluate_condition(&rule.condition, facts)? {
-                println!("规则触发: {} ({})", rule.name, rule.id);
-                for action in &rule.actions {
-                    self.execute_action(action, &mut context)?;
-                }
+
+        // 记录 (rule_id, 事实状态哈希) 已触发过的组合，防止同一状态下反复触发
+        let mut fired: HashSet<(String, u64)> = HashSet::new();
+        let mut cycle = 0usize;
+
+        loop {
+            let Some((rule, mut context)) = self.begin_cycle(&sorted_rules, &working, &outputs, &mut fired, cycle)? else {
+                break;
+            };
+
+            for action in &rule.actions {
+                self.execute_action(action, &mut context)?;
             }
+            Self::finish_cycle(&mut working, &mut outputs, context);
+
+            cycle += 1;
         }
-        
-        Ok(context.outputs)
+
+        Ok(outputs)
     }
 }
 
@@ -383,6 +794,8 @@ fn main() {
                 ("transaction_id".to_string(), Value::String("txn12345".to_string())),
                 ("amount".to_string(), Value::Int(15000)),
             ]),
+            timeout_ms: None,
+            max_retries: None,
         })
         .build();
     
@@ -409,6 +822,8 @@ fn main() {
                 ("discount".to_string(), Value::Float(0.15)),
                 ("rule".to_string(), Value::String("大额折扣".to_string())),
             ]),
+            timeout_ms: None,
+            max_retries: None,
         })
         .build();
     
@@ -433,4 +848,158 @@ fn main() {
             eprintln!("规则执行错误: {}", e);
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts(pairs: Vec<(&str, Value)>) -> HashMap<String, Value> {
+        pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+    }
+
+    #[test]
+    fn regex_captures_are_discarded_when_condition_overall_false() {
+        let mut engine = RuleExecutor::new();
+        let rule = RuleBuilder::new("r1", "提取邮箱用户名")
+            .condition(Condition::And(vec![
+                Condition::Regex {
+                    field: "email".to_string(),
+                    pattern: r"(?P<user>[^@]+)@(?P<domain>.+)".to_string(),
+                    ignore_case: false,
+                },
+                Condition::Equals { field: "must_be_true".to_string(), value: Value::Bool(true) },
+            ]))
+            .action(Action::Log { message: "fired".to_string() })
+            .build();
+        engine.add_rule(rule);
+
+        let input = facts(vec![
+            ("email", Value::String("alice@example.com".to_string())),
+            ("must_be_true", Value::Bool(false)),
+        ]);
+
+        let outputs = engine.execute(&input).unwrap();
+        assert!(!outputs.contains_key("email_user"));
+    }
+
+    #[test]
+    fn regex_captures_are_kept_when_condition_overall_true() {
+        let mut engine = RuleExecutor::new();
+        let rule = RuleBuilder::new("r1", "提取邮箱用户名")
+            .condition(Condition::And(vec![
+                Condition::Regex {
+                    field: "email".to_string(),
+                    pattern: r"(?P<user>[^@]+)@(?P<domain>.+)".to_string(),
+                    ignore_case: false,
+                },
+                Condition::Equals { field: "must_be_true".to_string(), value: Value::Bool(true) },
+            ]))
+            .action(Action::Log { message: "fired".to_string() })
+            .build();
+        engine.add_rule(rule);
+
+        let input = facts(vec![
+            ("email", Value::String("alice@example.com".to_string())),
+            ("must_be_true", Value::Bool(true)),
+        ]);
+
+        let outputs = engine.execute(&input).unwrap();
+        assert_eq!(outputs.get("email_user"), Some(&Value::String("alice".to_string())));
+        assert_eq!(outputs.get("email_domain"), Some(&Value::String("example.com".to_string())));
+    }
+
+    #[test]
+    fn forward_chaining_retriggers_on_update_field() {
+        let mut engine = RuleExecutor::new();
+        let rule_a = RuleBuilder::new("a", "设置标志位")
+            .priority(100)
+            .condition(Condition::Equals { field: "start".to_string(), value: Value::Bool(true) })
+            .action(Action::UpdateField { field: "flag".to_string(), value: Value::Bool(true) })
+            .build();
+        let rule_b = RuleBuilder::new("b", "响应标志位")
+            .priority(50)
+            .condition(Condition::Equals { field: "flag".to_string(), value: Value::Bool(true) })
+            .action(Action::UpdateField { field: "reacted".to_string(), value: Value::Bool(true) })
+            .build();
+        engine.add_rule(rule_a);
+        engine.add_rule(rule_b);
+
+        let input = facts(vec![("start", Value::Bool(true))]);
+        let outputs = engine.execute(&input).unwrap();
+
+        assert_eq!(outputs.get("flag"), Some(&Value::Bool(true)));
+        assert_eq!(outputs.get("reacted"), Some(&Value::Bool(true)));
+    }
+
+    /// 每次执行都把 `counter` 加一，事实集每轮都变成新状态，规则集永不收敛
+    struct IncrementCounter;
+
+    impl ActionHandler for IncrementCounter {
+        fn run(&self, _params: &HashMap<String, Value>, ctx: &mut RuleContext) -> Result<(), RuleEngineError> {
+            let next = match ctx.facts.get("counter") {
+                Some(Value::Int(i)) => i + 1,
+                _ => 0,
+            };
+            ctx.outputs.insert("counter".to_string(), Value::Int(next));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn non_convergent_rules_exceed_max_cycles() {
+        let mut engine = RuleExecutor::new().with_max_cycles(5);
+        engine.register_action("increment", Arc::new(IncrementCounter));
+        let increment = RuleBuilder::new("increment", "计数器自增")
+            .condition(Condition::And(vec![]))
+            .action(Action::Custom { name: "increment".to_string(), params: HashMap::new() })
+            .build();
+        engine.add_rule(increment);
+
+        let input = facts(vec![("counter", Value::Int(0))]);
+        let err = engine.execute(&input).unwrap_err();
+        assert!(matches!(err, RuleEngineError::ExecutionError(_)));
+    }
+
+    #[test]
+    fn many_skipped_rules_in_one_sweep_do_not_exhaust_cycle_budget() {
+        let mut engine = RuleExecutor::new().with_max_cycles(3);
+        for i in 0..150 {
+            let rule = RuleBuilder::new(&format!("skip{}", i), "从不匹配")
+                .condition(Condition::Equals { field: "never".to_string(), value: Value::Bool(true) })
+                .action(Action::Log { message: "不应触发".to_string() })
+                .build();
+            engine.add_rule(rule);
+        }
+        let matching = RuleBuilder::new("match", "匹配")
+            .condition(Condition::Equals { field: "go".to_string(), value: Value::Bool(true) })
+            .action(Action::UpdateField { field: "done".to_string(), value: Value::Bool(true) })
+            .build();
+        engine.add_rule(matching);
+
+        let input = facts(vec![
+            ("never", Value::Bool(false)),
+            ("go", Value::Bool(true)),
+        ]);
+
+        let outputs = engine.execute(&input).unwrap();
+        assert_eq!(outputs.get("done"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn hash_facts_is_independent_of_map_construction_order() {
+        let mut a = HashMap::new();
+        a.insert("x".to_string(), Value::Int(1));
+        a.insert("y".to_string(), Value::Int(2));
+        a.insert("z".to_string(), Value::Int(3));
+        let nested_a = facts(vec![("inner", Value::Map(a))]);
+
+        let mut b = HashMap::new();
+        b.insert("z".to_string(), Value::Int(3));
+        b.insert("x".to_string(), Value::Int(1));
+        b.insert("y".to_string(), Value::Int(2));
+        let nested_b = facts(vec![("inner", Value::Map(b))]);
+
+        assert_eq!(RuleExecutor::hash_facts(&nested_a), RuleExecutor::hash_facts(&nested_b));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,376 @@
+//! 手写的规则解析器：把 `serde_json::Value`（或等价的 YAML 文档）转换为
+//! `Rule`，而不是依赖 `#[derive(Deserialize)]`。相比derive，这里能在缺少
+//! 必需字段、条件/动作类型不认识时给出指向具体规则ID与JSON路径的报错，
+//! 便于从配置文件里加载/热更新规则集时定位问题。
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::{Action, Condition, Rule, RuleEngineError, Value};
+
+/// `RuleExecutor::load_rules_from_str` 支持的文本格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleFormat {
+    Json,
+    Yaml,
+}
+
+fn invalid_format(rule_id: &str, json_path: &str, message: impl Into<String>) -> RuleEngineError {
+    RuleEngineError::InvalidRuleFormat(format!("规则 '{}' 在 {} 处无效: {}", rule_id, json_path, message.into()))
+}
+
+fn as_object<'a>(json: &'a serde_json::Value, rule_id: &str, json_path: &str) -> Result<&'a serde_json::Map<String, serde_json::Value>, RuleEngineError> {
+    json.as_object().ok_or_else(|| invalid_format(rule_id, json_path, "必须是一个JSON对象"))
+}
+
+fn require<'a>(
+    obj: &'a serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<&'a serde_json::Value, RuleEngineError> {
+    obj.get(key).ok_or_else(|| invalid_format(rule_id, json_path, format!("缺少必需字段 '{}'", key)))
+}
+
+fn require_str(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<String, RuleEngineError> {
+    require(obj, key, rule_id, json_path)?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| invalid_format(rule_id, json_path, format!("字段 '{}' 必须是字符串", key)))
+}
+
+fn require_array<'a>(
+    obj: &'a serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<&'a Vec<serde_json::Value>, RuleEngineError> {
+    require(obj, key, rule_id, json_path)?
+        .as_array()
+        .ok_or_else(|| invalid_format(rule_id, json_path, format!("字段 '{}' 必须是数组", key)))
+}
+
+fn params_map(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<HashMap<String, Value>, RuleEngineError> {
+    match obj.get(key) {
+        None => Ok(HashMap::new()),
+        Some(serde_json::Value::Object(map)) => match Value::from_json(&serde_json::Value::Object(map.clone())) {
+            Value::Map(map) => Ok(map),
+            _ => unreachable!("Value::from_json(Object) 总是产生 Value::Map"),
+        },
+        Some(_) => Err(invalid_format(rule_id, &format!("{}.{}", json_path, key), "必须是一个JSON对象")),
+    }
+}
+
+/// 解析 `equals`/`greater_than`/`less_than`/`contains` 这类“字段-值”比较条件
+fn parse_comparison_condition(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    rule_id: &str,
+    json_path: &str,
+    cond_type: &str,
+) -> Result<Condition, RuleEngineError> {
+    let field = require_str(obj, "field", rule_id, json_path)?;
+    let raw_value = require(obj, "value", rule_id, json_path)?;
+    let value = Value::from_json(raw_value);
+
+    Ok(match cond_type {
+        "equals" => Condition::Equals { field, value },
+        "greater_than" => Condition::GreaterThan { field, value },
+        "less_than" => Condition::LessThan { field, value },
+        "contains" => Condition::Contains { field, value },
+        _ => unreachable!("parse_comparison_condition 只应被已知的比较类型调用"),
+    })
+}
+
+/// 解析正则条件，对应 ActivityWatch 风格的 `parse_regex_rule`
+fn parse_regex_rule(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<Condition, RuleEngineError> {
+    let field = require_str(obj, "field", rule_id, json_path)?;
+    let pattern = require_str(obj, "pattern", rule_id, json_path)?;
+    let ignore_case = obj.get("ignore_case").and_then(|v| v.as_bool()).unwrap_or(false);
+    Ok(Condition::Regex { field, pattern, ignore_case })
+}
+
+fn parse_custom_condition(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<Condition, RuleEngineError> {
+    let name = require_str(obj, "name", rule_id, json_path)?;
+    let params = params_map(obj, "params", rule_id, json_path)?;
+    Ok(Condition::Custom { name, params })
+}
+
+/// 解析 `and`/`or` 这类携带子规则数组的逻辑组合，对应ActivityWatch风格的 `parse_logical_rule`
+fn parse_logical_rule(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    rule_id: &str,
+    json_path: &str,
+    is_and: bool,
+) -> Result<Condition, RuleEngineError> {
+    let rules = require_array(obj, "rules", rule_id, json_path)?;
+    let parsed = rules.iter().enumerate()
+        .map(|(i, c)| parse_condition(c, rule_id, &format!("{}.rules[{}]", json_path, i)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(if is_and { Condition::And(parsed) } else { Condition::Or(parsed) })
+}
+
+fn parse_not_condition(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    rule_id: &str,
+    json_path: &str,
+) -> Result<Condition, RuleEngineError> {
+    let inner = require(obj, "rule", rule_id, json_path)?;
+    let parsed = parse_condition(inner, rule_id, &format!("{}.rule", json_path))?;
+    Ok(Condition::Not(Box::new(parsed)))
+}
+
+fn parse_condition(json: &serde_json::Value, rule_id: &str, json_path: &str) -> Result<Condition, RuleEngineError> {
+    let obj = as_object(json, rule_id, json_path)?;
+    let cond_type = require_str(obj, "type", rule_id, json_path)?;
+
+    match cond_type.as_str() {
+        "equals" | "greater_than" | "less_than" | "contains" => parse_comparison_condition(obj, rule_id, json_path, &cond_type),
+        "regex" => parse_regex_rule(obj, rule_id, json_path),
+        "custom" => parse_custom_condition(obj, rule_id, json_path),
+        "and" => parse_logical_rule(obj, rule_id, json_path, true),
+        "or" => parse_logical_rule(obj, rule_id, json_path, false),
+        "not" => parse_not_condition(obj, rule_id, json_path),
+        other => Err(invalid_format(rule_id, json_path, format!("未知的条件类型 '{}'", other))),
+    }
+}
+
+fn parse_action(json: &serde_json::Value, rule_id: &str, json_path: &str) -> Result<Action, RuleEngineError> {
+    let obj = as_object(json, rule_id, json_path)?;
+    let action_type = require_str(obj, "type", rule_id, json_path)?;
+
+    match action_type.as_str() {
+        "log" => Ok(Action::Log { message: require_str(obj, "message", rule_id, json_path)? }),
+
+        "update_field" => Ok(Action::UpdateField {
+            field: require_str(obj, "field", rule_id, json_path)?,
+            value: Value::from_json(require(obj, "value", rule_id, json_path)?),
+        }),
+
+        "call_external_service" => Ok(Action::CallExternalService {
+            endpoint: require_str(obj, "endpoint", rule_id, json_path)?,
+            payload: params_map(obj, "payload", rule_id, json_path)?,
+            timeout_ms: obj.get("timeout_ms").and_then(|v| v.as_u64()),
+            max_retries: obj.get("max_retries").and_then(|v| v.as_u64()).map(|v| v as u32),
+        }),
+
+        "send_event" => Ok(Action::SendEvent {
+            event_type: require_str(obj, "event_type", rule_id, json_path)?,
+            data: params_map(obj, "data", rule_id, json_path)?,
+            timeout_ms: obj.get("timeout_ms").and_then(|v| v.as_u64()),
+            max_retries: obj.get("max_retries").and_then(|v| v.as_u64()).map(|v| v as u32),
+        }),
+
+        "custom" => Ok(Action::Custom {
+            name: require_str(obj, "name", rule_id, json_path)?,
+            params: params_map(obj, "params", rule_id, json_path)?,
+        }),
+
+        "composite" => {
+            let actions = require_array(obj, "actions", rule_id, json_path)?;
+            let parsed = actions.iter().enumerate()
+                .map(|(i, a)| parse_action(a, rule_id, &format!("{}.actions[{}]", json_path, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Action::Composite(parsed))
+        }
+
+        other => Err(invalid_format(rule_id, json_path, format!("未知的动作类型 '{}'", other))),
+    }
+}
+
+fn parse_rule(json: &serde_json::Value) -> Result<Rule, RuleEngineError> {
+    let obj = json.as_object().ok_or_else(|| RuleEngineError::InvalidRuleFormat(
+        "规则必须是一个JSON对象".to_string()
+    ))?;
+
+    // 先取出 id，后续报错都带上它以便定位
+    let id = require_str(obj, "id", "<unknown>", "$.id")?;
+    let name = require_str(obj, "name", &id, "$.name")?;
+    let description = obj.get("description").and_then(|v| v.as_str()).map(String::from);
+    let priority = obj.get("priority").and_then(|v| v.as_u64()).unwrap_or(50) as u32;
+    let enabled = obj.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true);
+
+    let condition_json = require(obj, "condition", &id, "$.condition")?;
+    let condition = parse_condition(condition_json, &id, "$.condition")?;
+
+    let actions_json = require_array(obj, "actions", &id, "$.actions")?;
+    let actions = actions_json.iter().enumerate()
+        .map(|(i, a)| parse_action(a, &id, &format!("$.actions[{}]", i)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Rule { id, name, description, priority, condition, actions, enabled })
+}
+
+impl TryFrom<serde_json::Value> for Rule {
+    type Error = RuleEngineError;
+
+    fn try_from(json: serde_json::Value) -> Result<Self, Self::Error> {
+        parse_rule(&json)
+    }
+}
+
+/// 把一份 JSON/YAML 文本解析为若干 `Rule`。文本既可以是单条规则对象，也可以是规则数组。
+pub fn parse_rules_from_str(format: RuleFormat, text: &str) -> Result<Vec<Rule>, RuleEngineError> {
+    let json: serde_json::Value = match format {
+        RuleFormat::Json => serde_json::from_str(text)
+            .map_err(|e| RuleEngineError::ParseError(format!("JSON解析失败: {}", e)))?,
+        RuleFormat::Yaml => {
+            let yaml: serde_yaml::Value = serde_yaml::from_str(text)
+                .map_err(|e| RuleEngineError::ParseError(format!("YAML解析失败: {}", e)))?;
+            serde_json::to_value(yaml)
+                .map_err(|e| RuleEngineError::ParseError(format!("YAML转换为内部表示失败: {}", e)))?
+        }
+    };
+
+    match json {
+        serde_json::Value::Array(items) => items.into_iter().map(Rule::try_from).collect(),
+        single @ serde_json::Value::Object(_) => Ok(vec![Rule::try_from(single)?]),
+        _ => Err(RuleEngineError::InvalidRuleFormat("规则文本的顶层必须是对象或数组".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_happy_path_rule_with_nested_conditions() {
+        let text = r#"{
+            "id": "r1",
+            "name": "高额交易",
+            "priority": 90,
+            "condition": {
+                "type": "and",
+                "rules": [
+                    {"type": "greater_than", "field": "amount", "value": 100},
+                    {"type": "equals", "field": "currency", "value": "USD"}
+                ]
+            },
+            "actions": [
+                {"type": "log", "message": "matched"},
+                {"type": "update_field", "field": "flag", "value": true}
+            ]
+        }"#;
+
+        let rules = parse_rules_from_str(RuleFormat::Json, text).unwrap();
+        assert_eq!(rules.len(), 1);
+        let rule = &rules[0];
+        assert_eq!(rule.id, "r1");
+        assert_eq!(rule.priority, 90);
+        assert!(matches!(rule.condition, Condition::And(ref conds) if conds.len() == 2));
+        assert_eq!(rule.actions.len(), 2);
+    }
+
+    #[test]
+    fn missing_required_field_names_rule_id_and_json_path() {
+        let text = r#"{
+            "id": "r1",
+            "condition": {"type": "equals", "field": "x", "value": 1},
+            "actions": []
+        }"#;
+
+        let err = parse_rules_from_str(RuleFormat::Json, text).unwrap_err();
+        let RuleEngineError::InvalidRuleFormat(message) = err else {
+            panic!("期望 InvalidRuleFormat，得到: {:?}", err);
+        };
+        assert!(message.contains("r1"));
+        assert!(message.contains("$.name"));
+    }
+
+    #[test]
+    fn logical_condition_without_rules_array_errors() {
+        let text = r#"{
+            "id": "r1",
+            "name": "坏的与条件",
+            "condition": {"type": "and"},
+            "actions": []
+        }"#;
+
+        let err = parse_rules_from_str(RuleFormat::Json, text).unwrap_err();
+        let RuleEngineError::InvalidRuleFormat(message) = err else {
+            panic!("期望 InvalidRuleFormat，得到: {:?}", err);
+        };
+        assert!(message.contains("r1"));
+        assert!(message.contains("$.condition"));
+    }
+
+    #[test]
+    fn unknown_condition_type_errors() {
+        let text = r#"{
+            "id": "r1",
+            "name": "未知条件",
+            "condition": {"type": "frobnicate"},
+            "actions": []
+        }"#;
+
+        let err = parse_rules_from_str(RuleFormat::Json, text).unwrap_err();
+        assert!(matches!(err, RuleEngineError::InvalidRuleFormat(_)));
+    }
+
+    #[test]
+    fn unknown_action_type_errors() {
+        let text = r#"{
+            "id": "r1",
+            "name": "未知动作",
+            "condition": {"type": "equals", "field": "x", "value": 1},
+            "actions": [{"type": "teleport"}]
+        }"#;
+
+        let err = parse_rules_from_str(RuleFormat::Json, text).unwrap_err();
+        assert!(matches!(err, RuleEngineError::InvalidRuleFormat(_)));
+    }
+
+    #[test]
+    fn non_object_payload_is_rejected_instead_of_defaulting_to_empty_map() {
+        let text = r#"{
+            "id": "r1",
+            "name": "坏的payload",
+            "condition": {"type": "equals", "field": "x", "value": 1},
+            "actions": [{
+                "type": "call_external_service",
+                "endpoint": "/x",
+                "payload": "not-an-object"
+            }]
+        }"#;
+
+        let err = parse_rules_from_str(RuleFormat::Json, text).unwrap_err();
+        let RuleEngineError::InvalidRuleFormat(message) = err else {
+            panic!("期望 InvalidRuleFormat，得到: {:?}", err);
+        };
+        assert!(message.contains("payload"));
+    }
+
+    #[test]
+    fn non_object_custom_params_is_rejected_instead_of_defaulting_to_empty_map() {
+        let text = r#"{
+            "id": "r1",
+            "name": "坏的params",
+            "condition": {"type": "custom", "name": "x", "params": [1, 2, 3]},
+            "actions": []
+        }"#;
+
+        let err = parse_rules_from_str(RuleFormat::Json, text).unwrap_err();
+        let RuleEngineError::InvalidRuleFormat(message) = err else {
+            panic!("期望 InvalidRuleFormat，得到: {:?}", err);
+        };
+        assert!(message.contains("params"));
+    }
+}
@@ -0,0 +1,286 @@
+//! 字段路径解析：支持 `a.b`、`items[0]`、`user.tags[2]` 这类嵌套/索引路径，
+//! 用于在 `Value::Map`/`Value::List` 构成的数据中读写深层字段。
+
+use std::collections::HashMap;
+
+use crate::{RuleEngineError, Value};
+
+/// 路径中的一步：按键进入 Map，或按下标进入 List
+#[derive(Debug, Clone, PartialEq)]
+enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+/// 将 `transaction.amount`、`items[0].price` 这样的字符串解析为一串路径步骤
+fn parse_path(path: &str) -> Result<Vec<PathStep>, RuleEngineError> {
+    let mut steps = Vec::new();
+    for segment in path.split('.') {
+        if segment.is_empty() {
+            return Err(RuleEngineError::EvaluationError(
+                format!("无效的字段路径: {}", path)
+            ));
+        }
+
+        let mut rest = segment;
+        // 先取出形如 `items` 的主键部分，再依次解析 `[0][1]` 之类的索引后缀
+        if let Some(bracket_pos) = rest.find('[') {
+            let key = &rest[..bracket_pos];
+            if !key.is_empty() {
+                steps.push(PathStep::Key(key.to_string()));
+            }
+            rest = &rest[bracket_pos..];
+        } else {
+            steps.push(PathStep::Key(rest.to_string()));
+            continue;
+        }
+
+        while !rest.is_empty() {
+            if !rest.starts_with('[') {
+                return Err(RuleEngineError::EvaluationError(
+                    format!("无效的字段路径: {}", path)
+                ));
+            }
+            let close = rest.find(']').ok_or_else(|| RuleEngineError::EvaluationError(
+                format!("无效的字段路径: {}", path)
+            ))?;
+            let index_str = &rest[1..close];
+            let index = index_str.parse::<usize>().map_err(|_| RuleEngineError::EvaluationError(
+                format!("无效的数组下标 '{}' (路径: {})", index_str, path)
+            ))?;
+            steps.push(PathStep::Index(index));
+            rest = &rest[close + 1..];
+        }
+    }
+
+    if steps.is_empty() {
+        return Err(RuleEngineError::EvaluationError(
+            format!("无效的字段路径: {}", path)
+        ));
+    }
+
+    Ok(steps)
+}
+
+fn get_step<'a>(value: &'a Value, step: &PathStep, path: &str) -> Result<&'a Value, RuleEngineError> {
+    match (value, step) {
+        (Value::Map(map), PathStep::Key(key)) => map.get(key).ok_or_else(|| {
+            RuleEngineError::EvaluationError(format!("字段路径不存在: {} (缺少键 '{}')", path, key))
+        }),
+        (Value::List(list), PathStep::Index(idx)) => list.get(*idx).ok_or_else(|| {
+            RuleEngineError::EvaluationError(format!("字段路径不存在: {} (下标 {} 越界)", path, idx))
+        }),
+        (Value::Map(_), PathStep::Index(_)) => Err(RuleEngineError::TypeMismatch(
+            format!("无法用数组下标访问Map类型 (路径: {})", path)
+        )),
+        (Value::List(_), PathStep::Key(_)) => Err(RuleEngineError::TypeMismatch(
+            format!("无法用键访问List类型 (路径: {})", path)
+        )),
+        _ => Err(RuleEngineError::TypeMismatch(
+            format!("字段路径 {} 指向的值既不是Map也不是List，无法继续深入", path)
+        )),
+    }
+}
+
+/// 在 `facts` 中按路径读取一个嵌套字段，第一段作为顶层键
+pub fn get_field_path<'a>(facts: &'a HashMap<String, Value>, path: &str) -> Result<&'a Value, RuleEngineError> {
+    let steps = parse_path(path)?;
+    let (first, rest) = steps.split_first().expect("parse_path 保证至少一个步骤");
+
+    let PathStep::Key(top_key) = first else {
+        return Err(RuleEngineError::EvaluationError(
+            format!("字段路径必须以字段名开头: {}", path)
+        ));
+    };
+
+    let mut current = facts.get(top_key).ok_or_else(|| {
+        RuleEngineError::EvaluationError(format!("字段不存在: {}", top_key))
+    })?;
+
+    for step in rest {
+        current = get_step(current, step, path)?;
+    }
+
+    Ok(current)
+}
+
+fn ensure_container_for(step: &PathStep) -> Value {
+    match step {
+        PathStep::Key(_) => Value::Map(HashMap::new()),
+        PathStep::Index(_) => Value::List(Vec::new()),
+    }
+}
+
+fn set_step(value: &mut Value, steps: &[PathStep], new_value: Value, path: &str) -> Result<(), RuleEngineError> {
+    let (step, rest) = steps.split_first().expect("set_step 在非空步骤上调用");
+
+    match step {
+        PathStep::Key(key) => {
+            if matches!(value, Value::Null) {
+                *value = Value::Map(HashMap::new());
+            }
+            let map = match value {
+                Value::Map(map) => map,
+                _ => return Err(RuleEngineError::TypeMismatch(
+                    format!("无法用键 '{}' 写入非Map类型 (路径: {})", key, path)
+                )),
+            };
+
+            if rest.is_empty() {
+                map.insert(key.clone(), new_value);
+                return Ok(());
+            }
+
+            let entry = map.entry(key.clone()).or_insert_with(|| ensure_container_for(&rest[0]));
+            set_step(entry, rest, new_value, path)
+        }
+        PathStep::Index(idx) => {
+            if matches!(value, Value::Null) {
+                *value = Value::List(Vec::new());
+            }
+            let list = match value {
+                Value::List(list) => list,
+                _ => return Err(RuleEngineError::TypeMismatch(
+                    format!("无法用下标 {} 写入非List类型 (路径: {})", idx, path)
+                )),
+            };
+
+            if list.len() <= *idx {
+                list.resize(*idx + 1, Value::Null);
+            }
+
+            if rest.is_empty() {
+                list[*idx] = new_value;
+                return Ok(());
+            }
+
+            if matches!(list[*idx], Value::Null) {
+                list[*idx] = ensure_container_for(&rest[0]);
+            }
+            set_step(&mut list[*idx], rest, new_value, path)
+        }
+    }
+}
+
+/// 按路径写入一个嵌套字段，第一段作为顶层键；沿途缺失的Map/List会被自动创建
+pub fn set_field_path(facts: &mut HashMap<String, Value>, path: &str, new_value: Value) -> Result<(), RuleEngineError> {
+    let steps = parse_path(path)?;
+    let (first, rest) = steps.split_first().expect("parse_path 保证至少一个步骤");
+
+    let PathStep::Key(top_key) = first else {
+        return Err(RuleEngineError::EvaluationError(
+            format!("字段路径必须以字段名开头: {}", path)
+        ));
+    };
+
+    if rest.is_empty() {
+        facts.insert(top_key.clone(), new_value);
+        return Ok(());
+    }
+
+    let entry = facts.entry(top_key.clone()).or_insert_with(|| ensure_container_for(&rest[0]));
+    set_step(entry, rest, new_value, path)
+}
+
+/// 与 `set_field_path` 相同，但在 `target` 里首次出现该路径的顶层键时，
+/// 会先从 `seed_from` 克隆该键当前的值作为起点，而不是凭空新建一个空
+/// Map/List。用于像 `RuleContext.outputs` 这样起初与 `facts` 不同步的写入
+/// 目标：否则仅因为某个顶层键此前还没在 `outputs` 里出现过，写入嵌套/索引
+/// 路径就会把该字段下未涉及的兄弟数据（其它Map键、List的其它下标）丢掉。
+pub fn set_field_path_with_seed(
+    target: &mut HashMap<String, Value>,
+    seed_from: &HashMap<String, Value>,
+    path: &str,
+    new_value: Value,
+) -> Result<(), RuleEngineError> {
+    let steps = parse_path(path)?;
+    let (first, _) = steps.split_first().expect("parse_path 保证至少一个步骤");
+
+    let PathStep::Key(top_key) = first else {
+        return Err(RuleEngineError::EvaluationError(
+            format!("字段路径必须以字段名开头: {}", path)
+        ));
+    };
+
+    if !target.contains_key(top_key) {
+        if let Some(seed) = seed_from.get(top_key) {
+            target.insert(top_key.clone(), seed.clone());
+        }
+    }
+
+    set_field_path(target, path, new_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nested_map_and_list() {
+        let mut item = HashMap::new();
+        item.insert("price".to_string(), Value::Int(10));
+        let mut facts = HashMap::new();
+        facts.insert("items".to_string(), Value::List(vec![Value::Map(item)]));
+
+        let value = get_field_path(&facts, "items[0].price").unwrap();
+        assert_eq!(value, &Value::Int(10));
+    }
+
+    #[test]
+    fn writes_create_intermediate_maps_and_lists() {
+        let mut facts = HashMap::new();
+        set_field_path(&mut facts, "user.tags[2]", Value::String("vip".to_string())).unwrap();
+
+        let value = get_field_path(&facts, "user.tags[2]").unwrap();
+        assert_eq!(value, &Value::String("vip".to_string()));
+    }
+
+    #[test]
+    fn errors_on_missing_path_step() {
+        let facts = HashMap::new();
+        let err = get_field_path(&facts, "transaction.amount").unwrap_err();
+        assert!(matches!(err, RuleEngineError::EvaluationError(_)));
+    }
+
+    #[test]
+    fn errors_on_accessor_type_mismatch() {
+        let mut facts = HashMap::new();
+        facts.insert("user".to_string(), Value::Map(HashMap::new()));
+
+        let err = get_field_path(&facts, "user[0]").unwrap_err();
+        assert!(matches!(err, RuleEngineError::TypeMismatch(_)));
+    }
+
+    #[test]
+    fn seeded_write_preserves_sibling_data_on_first_touch() {
+        let mut item = HashMap::new();
+        item.insert("price".to_string(), Value::Int(10));
+        item.insert("name".to_string(), Value::String("widget".to_string()));
+        let mut seed_from = HashMap::new();
+        seed_from.insert("items".to_string(), Value::List(vec![Value::Map(item)]));
+
+        let mut target = HashMap::new();
+        set_field_path_with_seed(&mut target, &seed_from, "items[0].price", Value::Int(5)).unwrap();
+
+        assert_eq!(get_field_path(&target, "items[0].price").unwrap(), &Value::Int(5));
+        assert_eq!(get_field_path(&target, "items[0].name").unwrap(), &Value::String("widget".to_string()));
+    }
+
+    #[test]
+    fn seeded_write_only_seeds_on_first_touch() {
+        let mut seed_user = HashMap::new();
+        seed_user.insert("from_seed".to_string(), Value::Int(999));
+        let mut seed_from = HashMap::new();
+        seed_from.insert("user".to_string(), Value::Map(seed_user));
+
+        let mut target_user = HashMap::new();
+        target_user.insert("existing".to_string(), Value::Int(1));
+        let mut target = HashMap::new();
+        target.insert("user".to_string(), Value::Map(target_user));
+        set_field_path_with_seed(&mut target, &seed_from, "user.added", Value::Int(2)).unwrap();
+
+        assert_eq!(get_field_path(&target, "user.added").unwrap(), &Value::Int(2));
+        assert_eq!(get_field_path(&target, "user.existing").unwrap(), &Value::Int(1));
+        assert!(get_field_path(&target, "user.from_seed").is_err());
+    }
+}
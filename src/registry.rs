@@ -0,0 +1,116 @@
+//! 自定义条件/动作的可插拔注册表。下游crate可以实现 `ConditionEvaluator` /
+//! `ActionHandler`，并通过 `RuleExecutor::register_condition` /
+//! `register_action` 注册到引擎中，而无需修改 `Condition`/`Action` 枚举本身。
+
+use std::collections::HashMap;
+
+use crate::{RuleContext, RuleEngineError, Value};
+
+/// 自定义条件的求值逻辑
+pub trait ConditionEvaluator: Send + Sync {
+    fn eval(&self, params: &HashMap<String, Value>, facts: &HashMap<String, Value>) -> Result<bool, RuleEngineError>;
+}
+
+/// 自定义动作的执行逻辑
+pub trait ActionHandler: Send + Sync {
+    fn run(&self, params: &HashMap<String, Value>, ctx: &mut RuleContext) -> Result<(), RuleEngineError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Condition, RuleBuilder, RuleExecutor};
+    use std::sync::Arc;
+
+    /// 判断 `facts["amount"]` 是否不小于 `params["min"]`
+    struct MinAmountCondition;
+
+    impl ConditionEvaluator for MinAmountCondition {
+        fn eval(&self, params: &HashMap<String, Value>, facts: &HashMap<String, Value>) -> Result<bool, RuleEngineError> {
+            let min = match params.get("min") {
+                Some(Value::Int(i)) => *i,
+                _ => return Err(RuleEngineError::EvaluationError("缺少整数参数 'min'".to_string())),
+            };
+            match facts.get("amount") {
+                Some(Value::Int(amount)) => Ok(*amount >= min),
+                _ => Err(RuleEngineError::EvaluationError("缺少字段 'amount'".to_string())),
+            }
+        }
+    }
+
+    /// 把 `params["value"]` 写入 `ctx.outputs["flagged"]`
+    struct FlagAction;
+
+    impl ActionHandler for FlagAction {
+        fn run(&self, params: &HashMap<String, Value>, ctx: &mut RuleContext) -> Result<(), RuleEngineError> {
+            let value = params.get("value").cloned().unwrap_or(Value::Null);
+            ctx.outputs.insert("flagged".to_string(), value);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_condition_is_dispatched_with_its_params() {
+        let mut engine = RuleExecutor::new();
+        engine.register_condition("min_amount", Arc::new(MinAmountCondition));
+
+        let rule = RuleBuilder::new("r1", "自定义条件规则")
+            .condition(Condition::Custom {
+                name: "min_amount".to_string(),
+                params: HashMap::from([("min".to_string(), Value::Int(100))]),
+            })
+            .action(Action::Log { message: "fired".to_string() })
+            .build();
+        engine.add_rule(rule);
+
+        let facts = HashMap::from([("amount".to_string(), Value::Int(150))]);
+        let outputs = engine.execute(&facts).unwrap();
+        assert!(outputs.is_empty());
+    }
+
+    #[test]
+    fn custom_action_mutates_rule_context_outputs() {
+        let mut engine = RuleExecutor::new();
+        engine.register_action("flag", Arc::new(FlagAction));
+
+        let rule = RuleBuilder::new("r1", "自定义动作规则")
+            .condition(Condition::Equals { field: "go".to_string(), value: Value::Bool(true) })
+            .action(Action::Custom {
+                name: "flag".to_string(),
+                params: HashMap::from([("value".to_string(), Value::String("yes".to_string()))]),
+            })
+            .build();
+        engine.add_rule(rule);
+
+        let facts = HashMap::from([("go".to_string(), Value::Bool(true))]);
+        let outputs = engine.execute(&facts).unwrap();
+        assert_eq!(outputs.get("flagged"), Some(&Value::String("yes".to_string())));
+    }
+
+    #[test]
+    fn unregistered_custom_condition_errors() {
+        let mut engine = RuleExecutor::new();
+        let rule = RuleBuilder::new("r1", "未注册条件")
+            .condition(Condition::Custom { name: "missing".to_string(), params: HashMap::new() })
+            .action(Action::Log { message: "不应执行".to_string() })
+            .build();
+        engine.add_rule(rule);
+
+        let err = engine.execute(&HashMap::new()).unwrap_err();
+        assert!(matches!(err, RuleEngineError::EvaluationError(_)));
+    }
+
+    #[test]
+    fn unregistered_custom_action_errors() {
+        let mut engine = RuleExecutor::new();
+        let rule = RuleBuilder::new("r1", "未注册动作")
+            .condition(Condition::Equals { field: "go".to_string(), value: Value::Bool(true) })
+            .action(Action::Custom { name: "missing".to_string(), params: HashMap::new() })
+            .build();
+        engine.add_rule(rule);
+
+        let facts = HashMap::from([("go".to_string(), Value::Bool(true))]);
+        let err = engine.execute(&facts).unwrap_err();
+        assert!(matches!(err, RuleEngineError::ActionFailed(_)));
+    }
+}
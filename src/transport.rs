@@ -0,0 +1,134 @@
+//! 外部服务调用的可插拔传输层。默认实现 `ReqwestTransport` 通过真实的 HTTP
+//! 请求来执行 `Action::CallExternalService` / `Action::SendEvent`，调用方也
+//! 可以实现 `Transport` 接入其它协议（消息队列、gRPC等）。
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{RuleEngineError, Value};
+
+/// 向某个 endpoint 发起一次调用并返回解析后的响应
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn call(
+        &self,
+        endpoint: &str,
+        payload: &HashMap<String, Value>,
+        timeout: Duration,
+    ) -> Result<Value, RuleEngineError>;
+}
+
+/// 默认传输实现：以 JSON 请求体向 `endpoint` 发起 HTTP POST
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Self {
+        Self { client: reqwest::Client::new() }
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn call(
+        &self,
+        endpoint: &str,
+        payload: &HashMap<String, Value>,
+        timeout: Duration,
+    ) -> Result<Value, RuleEngineError> {
+        let body = Value::Map(payload.clone()).to_json();
+
+        let response = self.client
+            .post(endpoint)
+            .json(&body)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| RuleEngineError::ActionFailed(format!("调用外部服务 {} 失败: {}", endpoint, e)))?
+            .error_for_status()
+            .map_err(|e| RuleEngineError::ActionFailed(format!("外部服务 {} 返回错误状态: {}", endpoint, e)))?;
+
+        let json = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| RuleEngineError::ActionFailed(format!("解析外部服务 {} 响应失败: {}", endpoint, e)))?;
+
+        Ok(Value::from_json(&json))
+    }
+}
+
+/// 带退避的重试调用：每次失败后等待时间翻倍，直到成功或用尽重试次数
+pub async fn call_with_retry(
+    transport: &dyn Transport,
+    endpoint: &str,
+    payload: &HashMap<String, Value>,
+    timeout: Duration,
+    max_retries: u32,
+) -> Result<Value, RuleEngineError> {
+    let mut attempt = 0;
+    loop {
+        match transport.call(endpoint, payload, timeout).await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                if attempt >= max_retries {
+                    return Err(err);
+                }
+                let backoff = Duration::from_millis(100 * 2u64.pow(attempt));
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// 一个前几次调用失败、之后成功的假传输，用来驱动重试逻辑而不依赖真实网络
+    struct FlakyTransport {
+        fail_times: u32,
+        attempts: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Transport for FlakyTransport {
+        async fn call(&self, _endpoint: &str, _payload: &HashMap<String, Value>, _timeout: Duration) -> Result<Value, RuleEngineError> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+            if attempt < self.fail_times {
+                Err(RuleEngineError::ActionFailed("模拟的传输失败".to_string()))
+            } else {
+                Ok(Value::String("ok".to_string()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_success_within_budget() {
+        let transport = FlakyTransport { fail_times: 2, attempts: AtomicU32::new(0) };
+        let result = call_with_retry(&transport, "/x", &HashMap::new(), Duration::from_millis(10), 5).await;
+
+        assert!(result.is_ok());
+        assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries_exhausted() {
+        let transport = FlakyTransport { fail_times: 10, attempts: AtomicU32::new(0) };
+        let result = call_with_retry(&transport, "/x", &HashMap::new(), Duration::from_millis(10), 2).await;
+
+        assert!(result.is_err());
+        // 首次调用 + 2 次重试 = 3 次尝试
+        assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+    }
+}